@@ -0,0 +1,172 @@
+//! The value domains the synthesizer can search over. `Expr` itself carries
+//! no values (its leaves are `Input`/`Zero` markers), so swapping domains
+//! is just a matter of picking what `Domain` implementation `eval_ast` and
+//! friends are instantiated with.
+
+/// A numeric domain the synthesizer can evaluate `Expr` trees over.
+pub trait Domain: Clone + PartialEq + std::fmt::Debug {
+    fn zero() -> Self;
+    fn inc(&self) -> Self;
+    fn half(&self) -> Self;
+    fn add(&self, rhs: &Self) -> Self;
+    fn mul(&self, rhs: &Self) -> Self;
+
+    /// A hashable fingerprint for observational-equivalence dedup, mirroring
+    /// `f64::to_bits` so values can live in a `HashSet` instead of a
+    /// linearly-scanned `Vec`.
+    fn to_bits(&self) -> u64;
+
+    /// Multiplicative identity "shaped like" self — 1 for scalars, the
+    /// identity matrix for Matrix. The default (zero incremented once) is
+    /// wrong for any domain whose zero() can't be trusted to carry over
+    /// self's context (Matrix's dimensions, ModInt's modulus), so both
+    /// override it.
+    fn identity(&self) -> Self {
+        Self::zero().inc()
+    }
+
+    /// True (associative) multiplication, as opposed to Domain::mul which
+    /// for Matrix is the elementwise Hadamard product. Defaults to
+    /// Domain::mul, which coincides with it for scalar domains; Matrix
+    /// overrides this with real matrix multiplication.
+    fn mat_mul(&self, rhs: &Self) -> Self {
+        self.mul(rhs)
+    }
+}
+
+impl Domain for f64 {
+    fn zero() -> Self {
+        0.0
+    }
+    fn inc(&self) -> Self {
+        self + 1.0
+    }
+    fn half(&self) -> Self {
+        self * 0.5
+    }
+    fn add(&self, rhs: &Self) -> Self {
+        self + rhs
+    }
+    fn mul(&self, rhs: &Self) -> Self {
+        self * rhs
+    }
+    fn to_bits(&self) -> u64 {
+        f64::to_bits(*self)
+    }
+}
+
+/// An integer modulo `modulus`, for number-theoretic targets like
+/// `f(x) = x^k mod p` that plain `f64` arithmetic can't express.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModInt {
+    pub val: u64,
+    pub modulus: u64,
+}
+
+/// The modulus `ModInt::zero()` assumes when no instance is around to copy
+/// one from — the usual competitive-programming prime, 1e9+7.
+pub const DEFAULT_MODULUS: u64 = 1_000_000_007;
+
+impl ModInt {
+    pub fn new(val: u64, modulus: u64) -> Self {
+        ModInt {
+            val: val % modulus,
+            modulus,
+        }
+    }
+}
+
+impl Domain for ModInt {
+    fn zero() -> Self {
+        ModInt::new(0, DEFAULT_MODULUS)
+    }
+    fn inc(&self) -> Self {
+        ModInt::new(self.val + 1, self.modulus)
+    }
+    fn half(&self) -> Self {
+        // Modular inverse of two via Fermat's little theorem (valid when
+        // `modulus` is an odd prime, e.g. the usual 1e9+7).
+        let inv_two = pow_mod(self.modulus - 2, ModInt::new(2, self.modulus));
+        self.mul(&inv_two)
+    }
+    fn add(&self, rhs: &Self) -> Self {
+        assert_eq!(self.modulus, rhs.modulus, "cannot add ModInts with different moduli");
+        ModInt::new(self.val + rhs.val, self.modulus)
+    }
+    fn mul(&self, rhs: &Self) -> Self {
+        assert_eq!(self.modulus, rhs.modulus, "cannot multiply ModInts with different moduli");
+        ModInt::new(self.val * rhs.val, self.modulus)
+    }
+    fn to_bits(&self) -> u64 {
+        self.val ^ self.modulus.rotate_left(32)
+    }
+
+    // Override the Domain::identity default: `Self::zero().inc()` would
+    // hardcode DEFAULT_MODULUS instead of carrying over self's modulus.
+    fn identity(&self) -> Self {
+        ModInt::new(1, self.modulus)
+    }
+}
+
+// Binary exponentiation used by ModInt::half to compute the modular
+// inverse of two via Fermat's little theorem.
+fn pow_mod(mut exp: u64, base: ModInt) -> ModInt {
+    let mut result = ModInt::new(1, base.modulus);
+    let mut base = base;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result.mul(&base);
+        }
+        base = base.mul(&base);
+        exp >>= 1;
+    }
+    return result;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modint_wraps_on_construction_and_add() {
+        let m = ModInt::new(5, 7);
+        assert_eq!(m.val, 5);
+        assert_eq!(m.add(&ModInt::new(4, 7)).val, 2); // (5 + 4) mod 7
+    }
+
+    #[test]
+    fn modint_mul_wraps() {
+        let a = ModInt::new(5, 7);
+        let b = ModInt::new(6, 7);
+        assert_eq!(a.mul(&b).val, 2); // 30 mod 7
+    }
+
+    #[test]
+    fn modint_half_is_modular_inverse_of_two() {
+        let ten = ModInt::new(10, DEFAULT_MODULUS);
+        assert_eq!(ten.half().val, 5);
+    }
+
+    #[test]
+    fn f64_domain_matches_plain_arithmetic() {
+        assert_eq!(f64::zero(), 0.0);
+        assert_eq!(3.0_f64.inc(), 4.0);
+        assert_eq!(3.0_f64.half(), 1.5);
+        assert_eq!(3.0_f64.add(&4.0), 7.0);
+        assert_eq!(3.0_f64.mul(&4.0), 12.0);
+    }
+
+    #[test]
+    fn modint_identity_carries_over_self_modulus() {
+        // Not DEFAULT_MODULUS: Domain::identity must not go through
+        // ModInt::zero(), which is hardcoded to DEFAULT_MODULUS.
+        let m = ModInt::new(5, 13);
+        assert_eq!(m.identity(), ModInt::new(1, 13));
+    }
+
+    #[test]
+    #[should_panic(expected = "different moduli")]
+    fn modint_mul_rejects_mismatched_moduli() {
+        ModInt::new(5, 13).mul(&ModInt::new(2, 7));
+    }
+}
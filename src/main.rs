@@ -1,7 +1,56 @@
+mod domain;
+mod matrix;
+mod sexpr;
+
+use std::collections::{HashMap, HashSet};
+
 use strum_macros::EnumIter;
 
+use domain::Domain;
+
 const DEBUG: bool = false;
-const MAX_SEARCH_DEPTH: usize = 6;
+// Hard cap on synthesized AST size (not generations). 11 covers every demo
+// in `main`, notably f(X)=7*X+1, whose shortest program under the
+// available primitives is 10 nodes.
+const MAX_PROGRAM_SIZE: usize = 11;
+// Exponents considered when growing Pow nodes; kept small since Pow's AST
+// size is O(1) regardless of k, so a wide range would blow up the bank.
+const POW_EXPONENTS: [u32; 3] = [2, 3, 4];
+
+// Which operators `grow` is allowed to introduce. Expr is one untyped
+// grammar shared by every Domain, so without this, searching a scalar
+// domain would waste its size budget growing Matrix-only nodes that, for
+// a scalar, just duplicate an already-considered op (Domain::mat_mul and
+// Domain::identity default to Domain::mul and "one"), and vice versa.
+//
+// `Zero` gates the nullary `Expr::Zero` terminal itself, not an operator:
+// `Domain::zero()` has no way to know what shape the caller's domain
+// values are (e.g. a Matrix's dimensions), so a domain whose zero() isn't
+// guaranteed to match every input's shape must leave it out rather than
+// have Add/Mul panic the first time a mismatched pair is combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Op {
+    Zero,
+    Inc,
+    Half,
+    Add,
+    Mul,
+    Pow,
+    MatMul,
+    MatPow,
+}
+
+// f64/ModInt: Domain::zero() always matches (there's only one "shape"),
+// plus plain arithmetic and scalar exponentiation.
+const SCALAR_OPS: [Op; 6] = [Op::Zero, Op::Inc, Op::Half, Op::Add, Op::Mul, Op::Pow];
+// Matrix: true matrix multiplication/exponentiation instead of the
+// elementwise Mul and the generic Pow (which, for a Matrix, multiplies
+// elementwise into an identity-matrix seed and so keeps only the
+// diagonal), which aren't what the linear-recurrence use case
+// (companion matrices, matrix powers) is searching for. Zero is left out
+// since Matrix::zero() is a fixed DEFAULT_MATRIX_DIM square and would
+// panic against an Input of any other shape.
+const MATRIX_OPS: [Op; 5] = [Op::Inc, Op::Half, Op::Add, Op::MatMul, Op::MatPow];
 
 #[derive(Debug, Default, EnumIter)]
 enum Expr {
@@ -12,6 +61,9 @@ enum Expr {
     Half(Box<Expr>), // Divide by two
     Add(Box<Expr>, Box<Expr>), // Addition
     Mul(Box<Expr>, Box<Expr>), // Multiplication
+    Pow(Box<Expr>, u32), // Exponentiation
+    MatMul(Box<Expr>, Box<Expr>), // Matrix multiplication
+    MatPow(Box<Expr>, u32), // Matrix exponentiation
 }
 
 // Implement Clone instead of Copy for Expr
@@ -24,142 +76,463 @@ impl Clone for Expr {
             Expr::Half(n) => Expr::Half(n.clone()),
             Expr::Add(lhs, rhs) => Expr::Add(lhs.clone(), rhs.clone()),
             Expr::Mul(lhs, rhs) => Expr::Mul(lhs.clone(), rhs.clone()),
+            Expr::Pow(n, exp) => Expr::Pow(n.clone(), *exp),
+            Expr::MatMul(lhs, rhs) => Expr::MatMul(lhs.clone(), rhs.clone()),
+            Expr::MatPow(n, exp) => Expr::MatPow(n.clone(), *exp),
         }
     }
 }
 
-// Evaluation function for the AST
-fn eval_ast(expr: &Expr, input: f64) -> f64 {
+// Binary exponentiation, generic over any Domain: halves the exponent each
+// step instead of multiplying in a loop, so evaluating Pow(_, k) costs
+// O(log k) domain multiplications. Reduction mod p for ModInt falls out of
+// Domain::mul already reducing its result.
+fn pow_domain<D: Domain>(base: D, mut exp: u32) -> D {
+    // Seeded from base.identity(), not D::zero().inc(): D::zero() can't know
+    // base's context (e.g. a ModInt's modulus), so seeding from it the way
+    // mat_pow_domain seeds from base.identity() below is what keeps this
+    // correct for any ModInt modulus instead of only DEFAULT_MODULUS.
+    let mut result = base.identity();
+    let mut base = base;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result.mul(&base);
+        }
+        base = base.mul(&base);
+        exp >>= 1;
+    }
+    return result;
+}
+
+// Binary exponentiation using true matrix multiplication, generic over any
+// Domain: the base case is Domain::identity() (the identity matrix, for a
+// Matrix) combined via Domain::mat_mul rather than the generic Pow's
+// elementwise Domain::mul.
+fn mat_pow_domain<D: Domain>(base: D, mut exp: u32) -> D {
+    let mut result = base.identity();
+    let mut base = base;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result.mat_mul(&base);
+        }
+        base = base.mat_mul(&base);
+        exp >>= 1;
+    }
+    return result;
+}
+
+// Evaluation function for the AST, generic over the value domain.
+fn eval_ast<D: Domain>(expr: &Expr, input: D) -> D {
     match expr {
-        Expr::Zero => 0.0,
-        Expr::Inc(n) => eval_ast(n, input) + 1.0,
-        Expr::Half(n) => eval_ast(n, input) * 0.5,
-        Expr::Add(lhs, rhs) => eval_ast(lhs, input) + eval_ast(rhs, input),
-        Expr::Mul(lhs, rhs) => eval_ast(lhs, input) * eval_ast(rhs, input),
+        Expr::Zero => D::zero(),
+        Expr::Inc(n) => eval_ast(n, input).inc(),
+        Expr::Half(n) => eval_ast(n, input).half(),
+        Expr::Add(lhs, rhs) => eval_ast(lhs, input.clone()).add(&eval_ast(rhs, input)),
+        Expr::Mul(lhs, rhs) => eval_ast(lhs, input.clone()).mul(&eval_ast(rhs, input)),
+        Expr::Pow(n, exp) => pow_domain(eval_ast(n, input), *exp),
+        Expr::MatMul(lhs, rhs) => eval_ast(lhs, input.clone()).mat_mul(&eval_ast(rhs, input)),
+        Expr::MatPow(n, exp) => mat_pow_domain(eval_ast(n, input), *exp),
         Expr::Input => input,
     }
 }
 
-// Generate next generation of expressions
-fn grow(plist: Vec<Expr>) -> Vec<Expr> {
-    let mut new_plist = plist.clone();
-    let mut product = Vec::new();
-    for item1 in plist.clone() {
-        for item2 in plist.clone() {
-            product.push((item1.clone(), item2.clone()));
+// Flat stack-machine instructions that an Expr lowers to. Running these
+// avoids the pointer chasing and match-per-node recursion of eval_ast,
+// which matters once the same program gets re-evaluated across every
+// input, over and over, generation after generation.
+#[derive(Debug, Clone, PartialEq)]
+enum Instr {
+    PushZero,
+    PushInput,
+    Inc,
+    Half,
+    Add,
+    Mul,
+    Pow(u32),
+    MatMul,
+    MatPow(u32),
+}
+
+// Lowers an Expr to bytecode via a post-order traversal: children are
+// emitted before the operator that consumes them.
+fn compile_expr(expr: &Expr) -> Vec<Instr> {
+    let mut program = Vec::new();
+    compile_into(expr, &mut program);
+    return program;
+}
+
+fn compile_into(expr: &Expr, program: &mut Vec<Instr>) {
+    match expr {
+        Expr::Zero => program.push(Instr::PushZero),
+        Expr::Input => program.push(Instr::PushInput),
+        Expr::Inc(n) => {
+            compile_into(n, program);
+            program.push(Instr::Inc);
+        }
+        Expr::Half(n) => {
+            compile_into(n, program);
+            program.push(Instr::Half);
+        }
+        Expr::Add(lhs, rhs) => {
+            compile_into(lhs, program);
+            compile_into(rhs, program);
+            program.push(Instr::Add);
+        }
+        Expr::Mul(lhs, rhs) => {
+            compile_into(lhs, program);
+            compile_into(rhs, program);
+            program.push(Instr::Mul);
+        }
+        Expr::Pow(n, exp) => {
+            compile_into(n, program);
+            program.push(Instr::Pow(*exp));
+        }
+        Expr::MatMul(lhs, rhs) => {
+            compile_into(lhs, program);
+            compile_into(rhs, program);
+            program.push(Instr::MatMul);
+        }
+        Expr::MatPow(n, exp) => {
+            compile_into(n, program);
+            program.push(Instr::MatPow(*exp));
         }
     }
+}
 
-    for (lhs, rhs) in product {
-        new_plist.push(Expr::Mul(Box::new(lhs.clone()), Box::new(rhs.clone())));
-        new_plist.push(Expr::Add(Box::new(lhs.clone()), Box::new(rhs.clone())));
-        new_plist.push(Expr::Inc(Box::new(lhs.clone())));
-        new_plist.push(Expr::Half(Box::new(lhs.clone())));
+// Runs a compiled program over a single operand stack, generic over the
+// value domain. Add/Mul pop two values and push one; Inc/Half/Pow mutate
+// or replace the value on top.
+fn eval_bytecode<D: Domain>(program: &[Instr], input: D) -> D {
+    let mut stack: Vec<D> = Vec::new();
+    for instr in program {
+        match instr {
+            Instr::PushZero => stack.push(D::zero()),
+            Instr::PushInput => stack.push(input.clone()),
+            Instr::Inc => {
+                let top = stack.last_mut().expect("Inc on empty stack");
+                *top = top.inc();
+            }
+            Instr::Half => {
+                let top = stack.last_mut().expect("Half on empty stack");
+                *top = top.half();
+            }
+            Instr::Add => {
+                let rhs = stack.pop().expect("Add missing rhs operand");
+                let lhs = stack.pop().expect("Add missing lhs operand");
+                stack.push(lhs.add(&rhs));
+            }
+            Instr::Mul => {
+                let rhs = stack.pop().expect("Mul missing rhs operand");
+                let lhs = stack.pop().expect("Mul missing lhs operand");
+                stack.push(lhs.mul(&rhs));
+            }
+            Instr::Pow(exp) => {
+                let base = stack.pop().expect("Pow missing base operand");
+                stack.push(pow_domain(base, *exp));
+            }
+            Instr::MatMul => {
+                let rhs = stack.pop().expect("MatMul missing rhs operand");
+                let lhs = stack.pop().expect("MatMul missing lhs operand");
+                stack.push(lhs.mat_mul(&rhs));
+            }
+            Instr::MatPow(exp) => {
+                let base = stack.pop().expect("MatPow missing base operand");
+                stack.push(mat_pow_domain(base, *exp));
+            }
+        }
     }
+    return stack.pop().expect("compiled program produced no result");
+}
 
-    if DEBUG {
-        println!("\n\n\ngrow returns: {:?}\n\n\n", new_plist);
+// Grows the next size class from a size-indexed bank: Inc/Half wrap each
+// child of size n-1, and Add/Mul combine every (lhs, rhs) pair whose sizes
+// split n-1, so every candidate this produces has AST size exactly n.
+// Only operators present in `ops` are considered.
+fn grow(bank: &HashMap<usize, Vec<Expr>>, n: usize, ops: &[Op]) -> Vec<Expr> {
+    let mut generation = Vec::new();
+
+    if let Some(children) = bank.get(&(n - 1)) {
+        for child in children {
+            if ops.contains(&Op::Inc) {
+                generation.push(Expr::Inc(Box::new(child.clone())));
+            }
+            if ops.contains(&Op::Half) {
+                generation.push(Expr::Half(Box::new(child.clone())));
+            }
+            if ops.contains(&Op::Pow) {
+                for exp in POW_EXPONENTS {
+                    generation.push(Expr::Pow(Box::new(child.clone()), exp));
+                }
+            }
+            if ops.contains(&Op::MatPow) {
+                for exp in POW_EXPONENTS {
+                    generation.push(Expr::MatPow(Box::new(child.clone()), exp));
+                }
+            }
+        }
     }
-    return new_plist;
-}
 
-// Remove expression equivalents for efficiency
-fn elim_equvalents(plist: Vec<Expr>, inputs: &Vec<f64>) -> Vec<Expr> {
-    let mut new_plist: Vec<Expr> = Vec::new();
-    let mut outputs_outcomes: Vec<Vec<f64>> = Vec::new();
-    for p in plist.clone() {
-        let res = inputs.iter().map(|inp| eval_ast(&p, *inp)).collect();
-        if !outputs_outcomes.contains(&res) {
-            outputs_outcomes.push(res);
-            new_plist.push(p);
+    if n >= 3 {
+        for i in 1..=(n - 2) {
+            let j = n - 1 - i;
+            if let (Some(lhs_bucket), Some(rhs_bucket)) = (bank.get(&i), bank.get(&j)) {
+                for lhs in lhs_bucket {
+                    for rhs in rhs_bucket {
+                        if ops.contains(&Op::Add) {
+                            generation.push(Expr::Add(Box::new(lhs.clone()), Box::new(rhs.clone())));
+                        }
+                        if ops.contains(&Op::Mul) {
+                            generation.push(Expr::Mul(Box::new(lhs.clone()), Box::new(rhs.clone())));
+                        }
+                        if ops.contains(&Op::MatMul) {
+                            generation.push(Expr::MatMul(Box::new(lhs.clone()), Box::new(rhs.clone())));
+                        }
+                    }
+                }
+            }
         }
     }
+
     if DEBUG {
-        println!("\n\n\nelim_equvalents returns: {:?}\n\n\n", new_plist);
+        println!("\n\n\ngrow({}) returns: {:?}\n\n\n", n, generation);
     }
-    return new_plist;
+    return generation;
 }
 
-fn synthesize(inputs: Vec<f64>, outputs: Vec<f64>) -> Expr {
+// Observational-equivalence signature: compiles once, runs the bytecode VM
+// over every input, and hashes each result via Domain::to_bits so the
+// signature can live in a HashSet instead of a linearly-scanned Vec.
+fn signature<D: Domain>(expr: &Expr, inputs: &Vec<D>) -> Vec<u64> {
+    let program = compile_expr(expr);
+    return inputs
+        .iter()
+        .map(|inp| eval_bytecode(&program, inp.clone()).to_bits())
+        .collect();
+}
+
+// Checks a candidate against the full dataset, not just the first example.
+fn matches_dataset<D: Domain>(expr: &Expr, inputs: &Vec<D>, outputs: &Vec<D>) -> bool {
+    let program = compile_expr(expr);
+    return inputs
+        .iter()
+        .zip(outputs.iter())
+        .all(|(inp, out)| eval_bytecode(&program, inp.clone()) == *out);
+}
+
+fn synthesize<D: Domain>(inputs: Vec<D>, outputs: Vec<D>, ops: &[Op]) -> Expr {
     println!("Inputs -> Outputs: {:?} -> {:?}", &inputs, &outputs);
-    let input = inputs[0].clone();
-    let output = outputs[0].clone();
-
-    // List of terminals - basically expressions that have values
-    // they do not contain expressions themselves
-    let mut plist: Vec<Expr> = vec![Expr::Input, Expr::Zero];
-    // Iterate until program is synthesized
-    for _ in 0..MAX_SEARCH_DEPTH {
-        plist = grow(plist);
-        plist = elim_equvalents(plist, &inputs);
-        for p in plist.iter() {
-            // Evaluate for single input
-            let eval_res = eval_ast(&p, input);
-            if eval_res == output {
-                // Promissing program, try for the entire dataset
-                let res = inputs
-                    .iter()
-                    .zip(outputs.clone().into_iter())
-                    .find_map(|(inp, out)| match eval_ast(&p, *inp) == out {
-                        true => {
-                            return None;
-                        } // So far so good
-                        false => {
-                            return Some(1);
-                        } // Single fail is enough to disregard the program
-                    });
-                match res {
-                    Some(_) => continue,
-                    None => {
-                        println!("      Program: {:?}", p);
-                        return p.to_owned();
-                    }
-                }
+
+    let mut bank: HashMap<usize, Vec<Expr>> = HashMap::new();
+    let mut seen: HashSet<Vec<u64>> = HashSet::new();
+
+    // Terminals - basically expressions that have values, they do not
+    // contain expressions themselves. These seed the bank at size 1. Zero
+    // is only included when the domain opts in (see Op::Zero) since
+    // Domain::zero() can't always be trusted to match the input's shape.
+    let mut terminals = vec![Expr::Input];
+    if ops.contains(&Op::Zero) {
+        terminals.push(Expr::Zero);
+    }
+    let mut size_one = Vec::new();
+    for terminal in terminals {
+        if seen.insert(signature(&terminal, &inputs)) {
+            if matches_dataset(&terminal, &inputs, &outputs) {
+                println!("      Program: {:?}", terminal);
+                return terminal;
             }
+            size_one.push(terminal);
         }
     }
+    bank.insert(1, size_one);
+
+    // Grow larger size classes until a program matches the whole dataset.
+    for n in 2..=MAX_PROGRAM_SIZE {
+        let candidates = grow(&bank, n, ops);
+        let mut kept = Vec::new();
+        for candidate in candidates {
+            if !seen.insert(signature(&candidate, &inputs)) {
+                continue; // observationally equivalent to a program already kept
+            }
+            if matches_dataset(&candidate, &inputs, &outputs) {
+                println!("      Program: {:?}", candidate);
+                return candidate;
+            }
+            kept.push(candidate);
+        }
+        bank.insert(n, kept);
+    }
 
     println!(
-        "Could not synthesize function after {} steps.",
-        MAX_SEARCH_DEPTH
+        "Could not synthesize function within program size {}.",
+        MAX_PROGRAM_SIZE
     );
     return Expr::Zero;
 }
 
+// Reads whitespace-separated "<input> <output>" example pairs from `path`,
+// synthesizes a matching f64 program, and prints it as an S-expression.
+// Then drops into a tiny REPL where pasted `<s-expression> <input>` lines
+// get parsed and evaluated, so a printed program can be fed back in.
+fn run_cli(path: &str) {
+    let contents = std::fs::read_to_string(path).expect("failed to read example file");
+    let mut inputs: Vec<f64> = Vec::new();
+    let mut outputs: Vec<f64> = Vec::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut columns = line.split_whitespace();
+        let inp: f64 = columns
+            .next()
+            .expect("missing input column")
+            .parse()
+            .expect("input column is not a number");
+        let out: f64 = columns
+            .next()
+            .expect("missing output column")
+            .parse()
+            .expect("output column is not a number");
+        inputs.push(inp);
+        outputs.push(out);
+    }
+
+    let program = synthesize(inputs, outputs, &SCALAR_OPS);
+    println!("{}", sexpr::to_sexpr(&program));
+
+    println!("Enter `<s-expression> <input>` to evaluate it, or an empty line to quit.");
+    for line in std::io::stdin().lines() {
+        let line = line.expect("failed to read stdin");
+        let line = line.trim();
+        if line.is_empty() {
+            break;
+        }
+        let split_at = match line.rfind(char::is_whitespace) {
+            Some(i) => i,
+            None => {
+                println!("      error: expected `<s-expression> <input>`");
+                continue;
+            }
+        };
+        let (expr_text, input_text) = line.split_at(split_at);
+        match sexpr::parse_sexpr(expr_text.trim()) {
+            Ok(expr) => match input_text.trim().parse::<f64>() {
+                Ok(input) => println!("      = {}", eval_ast(&expr, input)),
+                Err(_) => println!("      error: input is not a number"),
+            },
+            Err(e) => println!("      error: {}", e),
+        }
+    }
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(path) = args.get(1) {
+        run_cli(path);
+        return;
+    }
+
+    // Each demo below asserts on eval_ast's result, not just println!'s it -
+    // a too-small MAX_PROGRAM_SIZE previously let `synthesize` give up and
+    // fall back to `Expr::Zero` here without anything noticing.
     println!("\nSynthesize f(X)=X function");
     let inputs: Vec<f64> = vec![1.0, 2.0, 3.0];
     let outputs: Vec<f64> = vec![1.0, 2.0, 3.0];
-    let program = synthesize(inputs, outputs);
-    println!("      Test program(10.0) = {}", eval_ast(&program, 10.0));
+    let program = synthesize(inputs, outputs, &SCALAR_OPS);
+    let result = eval_ast(&program, 10.0);
+    println!("      Test program(10.0) = {}", result);
+    assert_eq!(result, 10.0);
 
     println!("\nSynthesize f(X)=0 function");
     let inputs: Vec<f64> = vec![1.0, 2.0, 8.0];
     let outputs: Vec<f64> = vec![0.0, 0.0, 0.0];
-    let program = synthesize(inputs, outputs);
-    println!("      Test program(10.0) = {}", eval_ast(&program, 10.0));
+    let program = synthesize(inputs, outputs, &SCALAR_OPS);
+    let result = eval_ast(&program, 10.0);
+    println!("      Test program(10.0) = {}", result);
+    assert_eq!(result, 0.0);
 
     println!("\nSynthesize f(X)=X+1 function");
     let inputs: Vec<f64> = vec![1.0, 2.0, 15.0];
     let outputs: Vec<f64> = vec![2.0, 3.0, 16.0];
-    let program = synthesize(inputs, outputs);
-    println!("      Test program(10.0) = {}", eval_ast(&program, 10.0));
+    let program = synthesize(inputs, outputs, &SCALAR_OPS);
+    let result = eval_ast(&program, 10.0);
+    println!("      Test program(10.0) = {}", result);
+    assert_eq!(result, 11.0);
 
     println!("\nSynthesize f(X)=7*X+1 function");
     let inputs: Vec<f64> = vec![1.0, 2.0, 0.5];
     let outputs: Vec<f64> = vec![8.0, 15.0, 4.5];
-    let program = synthesize(inputs, outputs);
-    println!("      Test program(10.0) = {}", eval_ast(&program, 10.0));
+    let program = synthesize(inputs, outputs, &SCALAR_OPS);
+    let result = eval_ast(&program, 10.0);
+    println!("      Test program(10.0) = {}", result);
+    assert_eq!(result, 71.0);
 
     println!("\nSynthesize f(X)=0.5*X+1 function");
     let inputs: Vec<f64> = vec![2.0, 4.0, 8.0];
     let outputs: Vec<f64> = vec![2.0, 3.0, 5.0];
-    let program = synthesize(inputs, outputs);
-    println!("      Test program(10.0) = {}", eval_ast(&program, 10.0));
+    let program = synthesize(inputs, outputs, &SCALAR_OPS);
+    let result = eval_ast(&program, 10.0);
+    println!("      Test program(10.0) = {}", result);
+    assert_eq!(result, 6.0);
 
     println!("\nSynthesize f(X)=X**3 function");
     let inputs: Vec<f64> = vec![2.0, 4.0, 5.0];
     let outputs: Vec<f64> = vec![8.0, 64.0, 125.0];
-    let program = synthesize(inputs, outputs);
-    println!("      Test program(3.0) = {}", eval_ast(&program, 3.0));
+    let program = synthesize(inputs, outputs, &SCALAR_OPS);
+    let result = eval_ast(&program, 3.0);
+    println!("      Test program(3.0) = {}", result);
+    assert_eq!(result, 27.0);
+
+    println!("\nSynthesize f(X)=X^3 mod p function (ModInt domain)");
+    let modulus = domain::DEFAULT_MODULUS;
+    let inputs: Vec<domain::ModInt> = vec![2, 4, 5]
+        .into_iter()
+        .map(|v| domain::ModInt::new(v, modulus))
+        .collect();
+    let outputs: Vec<domain::ModInt> = vec![8, 64, 125]
+        .into_iter()
+        .map(|v| domain::ModInt::new(v, modulus))
+        .collect();
+    let program = synthesize(inputs, outputs, &SCALAR_OPS);
+    let result = eval_ast(&program, domain::ModInt::new(3, modulus));
+    println!("      Test program(3) = {:?}", result);
+    assert_eq!(result, domain::ModInt::new(27, modulus));
+
+    println!("\nSynthesize f(M)=M^2 function (Matrix domain, Fibonacci Q-matrix squared)");
+    let fib_q = matrix::Matrix::new(2, 2, vec![1.0, 1.0, 1.0, 0.0]);
+    let fib_q_squared = matrix::Matrix::new(2, 2, vec![2.0, 1.0, 1.0, 1.0]);
+    let inputs: Vec<matrix::Matrix> = vec![fib_q];
+    let outputs: Vec<matrix::Matrix> = vec![fib_q_squared.clone()];
+    let program = synthesize(inputs, outputs, &MATRIX_OPS);
+    let probe = matrix::Matrix::new(2, 2, vec![1.0, 1.0, 1.0, 0.0]);
+    let result = eval_ast(&program, probe);
+    println!("      Test program(Q) = {:?}", result);
+    assert_eq!(result, fib_q_squared);
+
+    // A 3x3 companion matrix, e.g. for an order-3 linear recurrence. Matrix's
+    // Domain::zero() is a fixed 2x2, so this is also a regression check that
+    // `synthesize` never mixes it into a search over non-2x2 matrices.
+    println!("\nSynthesize f(M)=M^2 function (3x3 Matrix domain, order-3 companion matrix)");
+    let companion = matrix::Matrix::new(3, 3, vec![1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+    let companion_squared = companion.mat_mul(&companion);
+    let inputs: Vec<matrix::Matrix> = vec![companion.clone()];
+    let outputs: Vec<matrix::Matrix> = vec![companion_squared.clone()];
+    let program = synthesize(inputs, outputs, &MATRIX_OPS);
+    let result = eval_ast(&program, companion);
+    println!("      Test program(C) = {:?}", result);
+    assert_eq!(result, companion_squared);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pow_domain_respects_non_default_modulus() {
+        // A modulus other than DEFAULT_MODULUS: pow_domain must seed its
+        // accumulator from base.identity(), not D::zero().inc(), or the
+        // first multiplication panics on mismatched moduli.
+        let base = domain::ModInt::new(5, 13);
+        assert_eq!(pow_domain(base, 3), domain::ModInt::new(8, 13)); // 5^3 mod 13 = 8
+    }
 }
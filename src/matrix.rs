@@ -0,0 +1,140 @@
+//! A small fixed-size matrix domain, dense enough to recover transition
+//! matrices for linear recurrences (Fibonacci-style sequences) via
+//! `Expr::MatMul`/`Expr::MatPow`.
+
+use crate::domain::Domain;
+
+/// The dimensions `Matrix::zero()` assumes when no instance is around to
+/// copy them from — a 2x2, the usual size for a linear-recurrence
+/// transition matrix.
+pub const DEFAULT_MATRIX_DIM: usize = 2;
+
+/// A dense, row-major matrix.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    pub rows: usize,
+    pub cols: usize,
+    pub data: Vec<f64>,
+}
+
+impl Matrix {
+    pub fn new(rows: usize, cols: usize, data: Vec<f64>) -> Self {
+        assert_eq!(
+            data.len(),
+            rows * cols,
+            "matrix data length must be rows * cols"
+        );
+        Matrix { rows, cols, data }
+    }
+
+    pub fn zeros(rows: usize, cols: usize) -> Self {
+        Matrix::new(rows, cols, vec![0.0; rows * cols])
+    }
+
+    pub fn identity(n: usize) -> Self {
+        let mut data = vec![0.0; n * n];
+        for i in 0..n {
+            data[i * n + i] = 1.0;
+        }
+        Matrix::new(n, n, data)
+    }
+
+    fn get(&self, row: usize, col: usize) -> f64 {
+        self.data[row * self.cols + col]
+    }
+}
+
+impl Domain for Matrix {
+    fn zero() -> Self {
+        Matrix::zeros(DEFAULT_MATRIX_DIM, DEFAULT_MATRIX_DIM)
+    }
+    fn inc(&self) -> Self {
+        Matrix::new(self.rows, self.cols, self.data.iter().map(|v| v + 1.0).collect())
+    }
+    fn half(&self) -> Self {
+        Matrix::new(self.rows, self.cols, self.data.iter().map(|v| v * 0.5).collect())
+    }
+    fn add(&self, rhs: &Self) -> Self {
+        assert_eq!(
+            (self.rows, self.cols),
+            (rhs.rows, rhs.cols),
+            "matrix addition requires matching dimensions"
+        );
+        let data = self.data.iter().zip(rhs.data.iter()).map(|(a, b)| a + b).collect();
+        Matrix::new(self.rows, self.cols, data)
+    }
+    fn mul(&self, rhs: &Self) -> Self {
+        // Elementwise (Hadamard) product; true matrix multiplication is
+        // Domain::mat_mul / Expr::MatMul below.
+        assert_eq!(
+            (self.rows, self.cols),
+            (rhs.rows, rhs.cols),
+            "elementwise product requires matching dimensions"
+        );
+        let data = self.data.iter().zip(rhs.data.iter()).map(|(a, b)| a * b).collect();
+        Matrix::new(self.rows, self.cols, data)
+    }
+    fn to_bits(&self) -> u64 {
+        // FNV-style fold of the flattened signature into one u64: multiply
+        // by the 64-bit FNV prime, then add each value's bits.
+        let seed = (self.rows as u64) ^ (self.cols as u64).rotate_left(16);
+        self.data.iter().fold(seed, |acc, v| {
+            acc.wrapping_mul(1_099_511_628_211).wrapping_add(v.to_bits())
+        })
+    }
+
+    fn identity(&self) -> Self {
+        assert_eq!(self.rows, self.cols, "identity is only defined for square matrices");
+        Matrix::identity(self.rows)
+    }
+    fn mat_mul(&self, rhs: &Self) -> Self {
+        assert_eq!(
+            self.cols, rhs.rows,
+            "matrix multiplication requires lhs.cols == rhs.rows"
+        );
+        let mut data = vec![0.0; self.rows * rhs.cols];
+        for row in 0..self.rows {
+            for col in 0..rhs.cols {
+                let mut sum = 0.0;
+                for k in 0..self.cols {
+                    sum += self.get(row, k) * rhs.get(k, col);
+                }
+                data[row * rhs.cols + col] = sum;
+            }
+        }
+        Matrix::new(self.rows, rhs.cols, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mat_mul_non_square() {
+        let a = Matrix::new(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let b = Matrix::new(3, 2, vec![7.0, 8.0, 9.0, 10.0, 11.0, 12.0]);
+        let product = a.mat_mul(&b);
+        assert_eq!(product, Matrix::new(2, 2, vec![58.0, 64.0, 139.0, 154.0]));
+    }
+
+    #[test]
+    fn mat_mul_differs_from_elementwise_mul() {
+        let fib_q = Matrix::new(2, 2, vec![1.0, 1.0, 1.0, 0.0]);
+        assert_eq!(fib_q.mat_mul(&fib_q), Matrix::new(2, 2, vec![2.0, 1.0, 1.0, 1.0]));
+        assert_eq!(fib_q.mul(&fib_q), Matrix::new(2, 2, vec![1.0, 1.0, 1.0, 0.0]));
+    }
+
+    #[test]
+    fn identity_is_mat_mul_neutral() {
+        let m = Matrix::new(3, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0]);
+        assert_eq!(m.mat_mul(&m.identity()), m);
+    }
+
+    #[test]
+    fn add_is_elementwise() {
+        let a = Matrix::new(2, 2, vec![1.0, 2.0, 3.0, 4.0]);
+        let b = Matrix::new(2, 2, vec![10.0, 20.0, 30.0, 40.0]);
+        assert_eq!(a.add(&b), Matrix::new(2, 2, vec![11.0, 22.0, 33.0, 44.0]));
+    }
+}
@@ -0,0 +1,195 @@
+//! A small S-expression frontend for `Expr`, so a synthesized program can be
+//! printed and pasted back in instead of living only as Rust `Debug` text.
+//! Forms look like `(add (mul input input) (inc zero))` or `(half input)`,
+//! with `input` and `zero` as atoms.
+
+use crate::Expr;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    UnexpectedEof,
+    UnexpectedToken(String),
+    UnknownForm(String),
+    UnknownAtom(String),
+    ExpectedCloseParen(String),
+    InvalidExponent(String),
+    TrailingTokens(String),
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::UnexpectedEof => write!(f, "unexpected end of input"),
+            ParseError::UnexpectedToken(t) => write!(f, "unexpected token `{}`", t),
+            ParseError::UnknownForm(t) => write!(f, "unknown form `{}`", t),
+            ParseError::UnknownAtom(t) => write!(f, "unknown atom `{}`", t),
+            ParseError::ExpectedCloseParen(t) => write!(f, "expected `)`, found `{}`", t),
+            ParseError::InvalidExponent(t) => write!(f, "invalid exponent `{}`", t),
+            ParseError::TrailingTokens(t) => write!(f, "trailing tokens after expression: `{}`", t),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c == '(' || c == ')' {
+            tokens.push(c.to_string());
+            chars.next();
+        } else if c.is_whitespace() {
+            chars.next();
+        } else {
+            let mut atom = String::new();
+            while let Some(&c) = chars.peek() {
+                if c == '(' || c == ')' || c.is_whitespace() {
+                    break;
+                }
+                atom.push(c);
+                chars.next();
+            }
+            tokens.push(atom);
+        }
+    }
+    return tokens;
+}
+
+fn parse_exponent(tokens: &[String], pos: &mut usize) -> Result<u32, ParseError> {
+    let token = tokens.get(*pos).ok_or(ParseError::UnexpectedEof)?;
+    let exp = token
+        .parse::<u32>()
+        .map_err(|_| ParseError::InvalidExponent(token.clone()))?;
+    *pos += 1;
+    return Ok(exp);
+}
+
+fn parse_form(tokens: &[String], pos: &mut usize) -> Result<Expr, ParseError> {
+    let token = tokens.get(*pos).ok_or(ParseError::UnexpectedEof)?.clone();
+    if token == ")" {
+        return Err(ParseError::UnexpectedToken(token));
+    }
+    if token != "(" {
+        *pos += 1;
+        return match token.as_str() {
+            "input" => Ok(Expr::Input),
+            "zero" => Ok(Expr::Zero),
+            other => Err(ParseError::UnknownAtom(other.to_string())),
+        };
+    }
+
+    *pos += 1; // consume "("
+    let head = tokens.get(*pos).ok_or(ParseError::UnexpectedEof)?.clone();
+    *pos += 1;
+    let expr = match head.as_str() {
+        "inc" => Expr::Inc(Box::new(parse_form(tokens, pos)?)),
+        "half" => Expr::Half(Box::new(parse_form(tokens, pos)?)),
+        "add" => {
+            let lhs = parse_form(tokens, pos)?;
+            let rhs = parse_form(tokens, pos)?;
+            Expr::Add(Box::new(lhs), Box::new(rhs))
+        }
+        "mul" => {
+            let lhs = parse_form(tokens, pos)?;
+            let rhs = parse_form(tokens, pos)?;
+            Expr::Mul(Box::new(lhs), Box::new(rhs))
+        }
+        "pow" => {
+            let base = parse_form(tokens, pos)?;
+            let exp = parse_exponent(tokens, pos)?;
+            Expr::Pow(Box::new(base), exp)
+        }
+        "matmul" => {
+            let lhs = parse_form(tokens, pos)?;
+            let rhs = parse_form(tokens, pos)?;
+            Expr::MatMul(Box::new(lhs), Box::new(rhs))
+        }
+        "matpow" => {
+            let base = parse_form(tokens, pos)?;
+            let exp = parse_exponent(tokens, pos)?;
+            Expr::MatPow(Box::new(base), exp)
+        }
+        other => return Err(ParseError::UnknownForm(other.to_string())),
+    };
+
+    let closing = tokens.get(*pos).ok_or(ParseError::UnexpectedEof)?.clone();
+    if closing != ")" {
+        return Err(ParseError::ExpectedCloseParen(closing));
+    }
+    *pos += 1;
+    return Ok(expr);
+}
+
+/// Parses a single S-expression into an `Expr`, e.g. `(add (mul input
+/// input) (inc zero))`.
+pub fn parse_sexpr(input: &str) -> Result<Expr, ParseError> {
+    let tokens = tokenize(input);
+    let mut pos = 0;
+    let expr = parse_form(&tokens, &mut pos)?;
+    if pos != tokens.len() {
+        return Err(ParseError::TrailingTokens(tokens[pos..].join(" ")));
+    }
+    return Ok(expr);
+}
+
+/// Prints an `Expr` as an S-expression; round-trips with `parse_sexpr`.
+pub fn to_sexpr(expr: &Expr) -> String {
+    match expr {
+        Expr::Input => "input".to_string(),
+        Expr::Zero => "zero".to_string(),
+        Expr::Inc(n) => format!("(inc {})", to_sexpr(n)),
+        Expr::Half(n) => format!("(half {})", to_sexpr(n)),
+        Expr::Add(lhs, rhs) => format!("(add {} {})", to_sexpr(lhs), to_sexpr(rhs)),
+        Expr::Mul(lhs, rhs) => format!("(mul {} {})", to_sexpr(lhs), to_sexpr(rhs)),
+        Expr::Pow(n, exp) => format!("(pow {} {})", to_sexpr(n), exp),
+        Expr::MatMul(lhs, rhs) => format!("(matmul {} {})", to_sexpr(lhs), to_sexpr(rhs)),
+        Expr::MatPow(n, exp) => format!("(matpow {} {})", to_sexpr(n), exp),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(expr: Expr) {
+        let printed = to_sexpr(&expr);
+        let parsed = parse_sexpr(&printed).expect("printed form should reparse");
+        assert_eq!(to_sexpr(&parsed), printed);
+    }
+
+    #[test]
+    fn round_trips_terminals() {
+        round_trip(Expr::Input);
+        round_trip(Expr::Zero);
+    }
+
+    #[test]
+    fn round_trips_nested_arithmetic() {
+        round_trip(Expr::Add(
+            Box::new(Expr::Mul(Box::new(Expr::Input), Box::new(Expr::Input))),
+            Box::new(Expr::Inc(Box::new(Expr::Zero))),
+        ));
+        round_trip(Expr::Pow(Box::new(Expr::Half(Box::new(Expr::Input))), 3));
+    }
+
+    #[test]
+    fn round_trips_matrix_ops() {
+        round_trip(Expr::MatMul(Box::new(Expr::Input), Box::new(Expr::Input)));
+        round_trip(Expr::MatPow(Box::new(Expr::Input), 2));
+    }
+
+    #[test]
+    fn parse_sexpr_reports_unknown_atom() {
+        assert_eq!(
+            parse_sexpr("bogus").unwrap_err(),
+            ParseError::UnknownAtom("bogus".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_sexpr_reports_trailing_tokens() {
+        assert_eq!(
+            parse_sexpr("input input").unwrap_err(),
+            ParseError::TrailingTokens("input".to_string())
+        );
+    }
+}